@@ -62,6 +62,63 @@ fn main() {
                 .multiple(true)
                 .help("Sets the level of verbosity"),
         )
+        .arg(
+            Arg::with_name("gas-price-block-window")
+                .long("gas-price-block-window")
+                .help("Number of recent blocks to sample when calibrating eth_gasPrice.")
+                .default_value("100")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("gas-price-percentile")
+                .long("gas-price-percentile")
+                .help("Percentile (0-100) of sampled transaction gas prices reported by eth_gasPrice.")
+                .default_value("60")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("gas-price-default")
+                .long("gas-price-default")
+                .help("Gas price (in wei) returned by eth_gasPrice until enough transactions have been sampled.")
+                .default_value("1000000000")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rate-limit-max-credits")
+                .long("rate-limit-max-credits")
+                .help("Maximum request credits a single connection may accumulate.")
+                .default_value("1000")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rate-limit-recharge-rate")
+                .long("rate-limit-recharge-rate")
+                .help("Request credits granted to a connection per second.")
+                .default_value("200")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("logs-max-block-range")
+                .long("logs-max-block-range")
+                .help("Maximum number of blocks an eth_getLogs filter may span.")
+                .default_value("1000")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("logs-default-limit")
+                .long("logs-default-limit")
+                .help("Default maximum number of logs returned by eth_getLogs when the filter does not specify one.")
+                .default_value("10000")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("webhook-url")
+                .long("webhook-url")
+                .help("HTTP endpoint to receive newHeads/logs/newPendingTransactions notifications. May be given multiple times.")
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true),
+        )
         .get_matches();
 
     // reset max log level to Info after default_app macro sets it to Trace
@@ -79,7 +136,22 @@ fn main() {
         .expect("failed to initialize component container");
 
     let num_threads = value_t!(args, "threads", usize).unwrap();
-    let client = web3_gateway::start(args, container, num_threads).unwrap();
+
+    let mut gateway_options = web3_gateway::config::GatewayOptions::default();
+    gateway_options.rate_limit.max_credits = value_t!(args, "rate-limit-max-credits", f64).unwrap();
+    gateway_options.rate_limit.recharge_rate =
+        value_t!(args, "rate-limit-recharge-rate", f64).unwrap();
+    gateway_options.webhook_urls = args.values_of("webhook-url")
+        .map(|values| values.map(String::from).collect())
+        .unwrap_or_default();
+    gateway_options.gas_price.block_window = value_t!(args, "gas-price-block-window", u64).unwrap();
+    gateway_options.gas_price.percentile = value_t!(args, "gas-price-percentile", usize).unwrap();
+    gateway_options.gas_price.default_price =
+        value_t!(args, "gas-price-default", u64).unwrap().into();
+    gateway_options.max_logs_range = value_t!(args, "logs-max-block-range", u64).unwrap();
+    gateway_options.default_logs_limit = value_t!(args, "logs-default-limit", usize).unwrap();
+
+    let client = web3_gateway::start(args, container, num_threads, gateway_options).unwrap();
 
     let exit = Arc::new((Mutex::new(false), Condvar::new()));
     CtrlC::set_handler({
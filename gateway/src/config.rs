@@ -0,0 +1,54 @@
+// Copyright 2015-2018 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! CLI-derived tunables threaded from `main` into `start`.
+//!
+//! Bundled into one struct so that adding another flag doesn't keep
+//! changing `start`'s parameter list.
+
+use gas_price::GasPriceCalibratorOptions;
+use rate_limit::CreditLimitOptions;
+
+/// Tunables parsed from CLI flags in `gateway/bin/main.rs`.
+#[derive(Debug, Clone)]
+pub struct GatewayOptions {
+    /// Per-connection request credit limiter configuration.
+    pub rate_limit: CreditLimitOptions,
+    /// HTTP endpoints that should receive a `WebhookNotifier`'s
+    /// newHeads/logs/newPendingTransactions payloads. Empty disables it.
+    pub webhook_urls: Vec<String>,
+    /// `eth_gasPrice` percentile-calibrator configuration.
+    pub gas_price: GasPriceCalibratorOptions,
+    /// Maximum number of blocks an `eth_getLogs` query may span; passed to
+    /// `EthClient::set_logs_limits`.
+    pub max_logs_range: u64,
+    /// Default `eth_getLogs` result limit when the filter specifies none;
+    /// passed to `EthClient::set_logs_limits`.
+    pub default_logs_limit: usize,
+}
+
+impl Default for GatewayOptions {
+    fn default() -> Self {
+        GatewayOptions {
+            rate_limit: CreditLimitOptions::default(),
+            webhook_urls: Vec::new(),
+            gas_price: GasPriceCalibratorOptions::default(),
+            // Mirrors `EthClient::with_config`'s hardcoded defaults.
+            max_logs_range: 1_000,
+            default_logs_limit: 10_000,
+        }
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2015-2018 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+// Mirrors the "calibrated" gas pricer in parity's `miner::gas_price_calibrator`.
+
+//! Percentile-based `eth_gasPrice` estimator.
+//!
+//! Periodically walks back over a window of recent blocks, collects the gas
+//! price of every transaction seen, and caches the value at a configurable
+//! percentile so `gas_price()` stays O(1) between refreshes.
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use ethcore::client::BlockChainClient;
+use ethcore::ids::BlockId;
+use ethereum_types::U256;
+use parking_lot::RwLock;
+
+use client::Client;
+
+/// Tunables for `GasPriceCalibrator`.
+#[derive(Debug, Clone)]
+pub struct GasPriceCalibratorOptions {
+    /// How many recent blocks to sample.
+    pub block_window: u64,
+    /// Percentile (0-100) of the sorted sample to report.
+    pub percentile: usize,
+    /// Minimum number of sampled transaction prices required before trusting
+    /// the percentile; below this, `default_price` is returned instead.
+    pub min_sample_count: usize,
+    /// Floor returned when too few transactions have been seen.
+    pub default_price: U256,
+    /// How often to recompute the cached price.
+    pub recalibration_interval: Duration,
+}
+
+impl Default for GasPriceCalibratorOptions {
+    fn default() -> Self {
+        GasPriceCalibratorOptions {
+            block_window: 100,
+            percentile: 60,
+            min_sample_count: 20,
+            default_price: U256::from(1_000_000_000u64), // 1 gwei
+            recalibration_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Caches a percentile-based gas price estimate, recomputed on a timer.
+pub struct GasPriceCalibrator {
+    options: GasPriceCalibratorOptions,
+    cached: Arc<RwLock<U256>>,
+}
+
+impl GasPriceCalibrator {
+    /// Creates a calibrator with `options` and starts its background
+    /// recalibration thread against `client`.
+    pub fn start(options: GasPriceCalibratorOptions, client: Arc<Client>) -> Self {
+        let cached = Arc::new(RwLock::new(options.default_price));
+
+        {
+            let cached = cached.clone();
+            let options = options.clone();
+            thread::spawn(move || loop {
+                let price = Self::calibrate(&options, &client);
+                *cached.write() = price;
+                thread::sleep(options.recalibration_interval);
+            });
+        }
+
+        GasPriceCalibrator { options, cached }
+    }
+
+    /// Returns the currently cached estimate; O(1).
+    pub fn gas_price(&self) -> U256 {
+        *self.cached.read()
+    }
+
+    fn calibrate(options: &GasPriceCalibratorOptions, client: &Client) -> U256 {
+        let best = client.best_block_number();
+        let earliest = best.saturating_sub(options.block_window.saturating_sub(1));
+
+        let mut prices = Vec::new();
+        let mut n = best;
+        loop {
+            if let Some(block) = client.block(BlockId::Number(n)) {
+                for tx in block.view().localized_transactions() {
+                    prices.push(tx.gas_price);
+                }
+            }
+
+            if n == earliest || n == 0 {
+                break;
+            }
+            n -= 1;
+        }
+
+        percentile_price(prices, options)
+    }
+}
+
+/// Picks `options.percentile` out of `prices` (sorted in place), or
+/// `options.default_price` if fewer than `options.min_sample_count` were
+/// collected. Pulled out of `calibrate` so it's testable without a `Client`.
+fn percentile_price(mut prices: Vec<U256>, options: &GasPriceCalibratorOptions) -> U256 {
+    if prices.len() < options.min_sample_count {
+        return options.default_price;
+    }
+
+    prices.sort();
+    let index = (prices.len() - 1) * options.percentile / 100;
+    prices[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> GasPriceCalibratorOptions {
+        GasPriceCalibratorOptions {
+            block_window: 100,
+            percentile: 60,
+            min_sample_count: 4,
+            default_price: U256::from(1_000_000_000u64),
+            recalibration_interval: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn below_min_sample_count_returns_default_price() {
+        let options = options();
+        let prices = vec![U256::from(1), U256::from(2), U256::from(3)];
+        assert_eq!(percentile_price(prices, &options), options.default_price);
+    }
+
+    #[test]
+    fn picks_requested_percentile_of_sorted_sample() {
+        let options = options();
+        // 10 samples, already out of order; 60th percentile of a
+        // zero-indexed sorted 10-element array is index 5 (value 6).
+        let prices = vec![10, 3, 7, 1, 9, 2, 8, 4, 6, 5]
+            .into_iter()
+            .map(U256::from)
+            .collect();
+        assert_eq!(percentile_price(prices, &options), U256::from(6));
+    }
+
+    #[test]
+    fn zero_percentile_picks_the_minimum() {
+        let mut options = options();
+        options.percentile = 0;
+        let prices = vec![U256::from(5), U256::from(1), U256::from(9), U256::from(4)];
+        assert_eq!(percentile_price(prices, &options), U256::from(1));
+    }
+
+    #[test]
+    fn hundredth_percentile_picks_the_maximum() {
+        let mut options = options();
+        options.percentile = 100;
+        let prices = vec![U256::from(5), U256::from(1), U256::from(9), U256::from(4)];
+        assert_eq!(percentile_price(prices, &options), U256::from(9));
+    }
+}
@@ -16,6 +16,7 @@
 
 //! Eth rpc implementation.
 
+use std::cmp;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::thread;
@@ -37,6 +38,14 @@ use jsonrpc_core::futures::future;
 use jsonrpc_core::{BoxFuture, Result};
 use jsonrpc_macros::Trailing;
 
+/// Size of the sub-ranges `logs()` scans the chain in. Deliberately decoupled
+/// from (and smaller than) `max_logs_range`: the latter bounds how wide a
+/// filter a caller may ask for, while this bounds how much work is done
+/// against the client per `BlockChainClient::logs` call, so a single wide
+/// request is still served as several smaller scans and can short-circuit
+/// early once the caller's limit is satisfied.
+const LOG_SCAN_CHUNK_SIZE: u64 = 100;
+
 use parity_rpc::v1::helpers::{errors, fake_sign, limit_logs};
 use parity_rpc::v1::metadata::Metadata;
 use parity_rpc::v1::traits::Eth;
@@ -45,6 +54,11 @@ use parity_rpc::v1::types::{block_number_to_id, Block, BlockNumber, BlockTransac
                             Index, Log, Receipt, RichBlock, SyncStatus, Transaction,
                             U256 as RpcU256, Work};
 
+use gas_price::{GasPriceCalibrator, GasPriceCalibratorOptions};
+use pool;
+use pool::verifier::BasicVerifier;
+use pool::{PoolConfig, Ready, SenderNonceReady, TransactionQueue, Verifier};
+
 // short for "try_boxfuture"
 // unwrap a result, returning a BoxFuture<_, Err> on failure.
 macro_rules! try_bf {
@@ -59,6 +73,13 @@ macro_rules! try_bf {
 /// Eth rpc implementation.
 pub struct EthClient {
     client: Arc<Client>,
+    pool: Arc<TransactionQueue>,
+    verifier: BasicVerifier<Arc<Client>>,
+    gas_price: Arc<GasPriceCalibrator>,
+    /// Maximum number of blocks an `eth_getLogs` query may span.
+    max_logs_range: u64,
+    /// Limit applied to `eth_getLogs` when the filter doesn't specify one.
+    default_logs_limit: usize,
 }
 
 #[derive(Debug)]
@@ -97,9 +118,78 @@ enum PendingTransactionId {
 impl EthClient where {
     /// Creates new EthClient.
     pub fn new(client: &Arc<Client>) -> Self {
+        EthClient::with_config(
+            client,
+            PoolConfig::default(),
+            GasPriceCalibratorOptions::default(),
+        )
+    }
+
+    /// Creates new EthClient with non-default transaction pool and gas price
+    /// calibrator configuration.
+    pub fn with_config(
+        client: &Arc<Client>,
+        pool_config: PoolConfig,
+        gas_price_options: GasPriceCalibratorOptions,
+    ) -> Self {
         EthClient {
             client: client.clone(),
+            pool: Arc::new(TransactionQueue::new(pool_config, Default::default())),
+            verifier: BasicVerifier::new(client.clone()),
+            gas_price: Arc::new(GasPriceCalibrator::start(gas_price_options, client.clone())),
+            max_logs_range: 1_000,
+            default_logs_limit: 10_000,
+        }
+    }
+
+    /// Sets the maximum `eth_getLogs` block range and default result limit.
+    pub fn set_logs_limits(&mut self, max_logs_range: u64, default_logs_limit: usize) {
+        self.max_logs_range = max_logs_range;
+        self.default_logs_limit = default_logs_limit;
+    }
+
+    /// Resolves a filter's `from_block`/`to_block` to concrete block
+    /// numbers against the current chain head.
+    fn resolve_log_range(&self, filter: &EthcoreFilter) -> Result<(u64, u64)> {
+        let best = self.client.best_block_number();
+
+        let resolve = |id: BlockId| -> Result<u64> {
+            match id {
+                BlockId::Number(n) => Ok(n),
+                BlockId::Earliest => Ok(0),
+                BlockId::Latest | BlockId::Pending => Ok(best),
+                BlockId::Hash(hash) => self.client
+                    .block_number(BlockId::Hash(hash))
+                    .ok_or_else(errors::unknown_block),
+            }
+        };
+
+        Ok((resolve(filter.from_block)?, resolve(filter.to_block)?))
+    }
+
+    /// Rejects filters whose resolved range is wider than `max_logs_range`.
+    fn check_log_range(&self, from: u64, to: u64) -> Result<()> {
+        if to >= from && to - from + 1 > self.max_logs_range {
+            return Err(errors::invalid_params(
+                "filter",
+                format!(
+                    "block range too wide: requested {} blocks, maximum allowed is {}",
+                    to - from + 1,
+                    self.max_logs_range
+                ),
+            ));
         }
+        Ok(())
+    }
+
+    /// Returns a handle to the local transaction pool, e.g. for wiring up
+    /// `parity_pendingTransactions` or pool-admission notifications.
+    pub fn pool(&self) -> Arc<TransactionQueue> {
+        self.pool.clone()
+    }
+
+    fn ready(&self) -> SenderNonceReady<Arc<Client>> {
+        SenderNonceReady::new(&self.client)
     }
 
     fn rich_block(&self, id: BlockNumberOrId, include_txs: bool) -> Result<Option<RichBlock>> {
@@ -183,13 +273,34 @@ impl EthClient where {
                 client_transaction(TransactionId::Location(block, index))
             }
 
-            // we don't have pending blocks
             PendingTransactionId::Location(PendingOrBlock::Pending, index) => {
-                return Ok(None);
+                Ok(self.pool.pending(self.ready()).get(index).map(|tx| {
+                    Self::transaction_from_pending(tx, self.client.best_block_number() + 1)
+                }))
             }
         }
     }
 
+    /// Builds an RPC `Transaction` for a pool transaction that has not yet
+    /// been included in a block: `block_hash`/`block_number`/
+    /// `transaction_index` are left unset, as geth and parity do for
+    /// pending transactions. Shared with `ParityClient::pending_transactions`,
+    /// which needs the same shaping for its own pool listing.
+    pub(crate) fn transaction_from_pending(
+        tx: &pool::VerifiedTransaction,
+        pending_block: u64,
+    ) -> Transaction {
+        let mut t = Transaction::from_signed(
+            tx.transaction.clone(),
+            pending_block,
+            Default::default(),
+        );
+        t.block_hash = None;
+        t.block_number = None;
+        t.transaction_index = None;
+        t
+    }
+
     fn uncle(&self, id: PendingUncleId) -> Result<Option<RichBlock>> {
         // we don't have uncles
         Ok(None)
@@ -248,8 +359,7 @@ impl Eth for EthClient {
     }
 
     fn gas_price(&self) -> Result<RpcU256> {
-        // TODO: gas model
-        Ok(RpcU256::from(0))
+        Ok(self.gas_price.gas_price().into())
     }
 
     fn accounts(&self, meta: Metadata) -> Result<Vec<RpcH160>> {
@@ -305,7 +415,10 @@ impl Eth for EthClient {
 
         let res = match num.unwrap_or_default() {
             BlockNumber::Pending => match self.client.nonce(&address, BlockId::Latest) {
-                Some(nonce) => Ok(nonce.into()),
+                Some(nonce) => {
+                    let ready_count = self.pool.ready_count_for_sender(&address, self.ready());
+                    Ok((nonce + U256::from(ready_count)).into())
+                }
                 None => Err(errors::database("latest nonce missing")),
             },
             number => {
@@ -442,12 +555,43 @@ impl Eth for EthClient {
     }
 
     fn logs(&self, filter: Filter) -> BoxFuture<Vec<Log>> {
-        let filter: EthcoreFilter = filter.into();
-        let mut logs = self.client
-            .logs(filter.clone())
-            .into_iter()
-            .map(From::from)
-            .collect::<Vec<Log>>();
+        let mut filter: EthcoreFilter = filter.into();
+        if filter.limit.is_none() {
+            filter.limit = Some(self.default_logs_limit);
+        }
+
+        let (from, to) = try_bf!(self.resolve_log_range(&filter));
+        try_bf!(self.check_log_range(from, to));
+
+        let chunk_size = cmp::min(LOG_SCAN_CHUNK_SIZE, self.max_logs_range);
+
+        let mut logs = Vec::new();
+        let mut chunk_start = from;
+        while chunk_start <= to {
+            let chunk_end = cmp::min(chunk_start + chunk_size - 1, to);
+
+            let mut chunk_filter = filter.clone();
+            chunk_filter.from_block = BlockId::Number(chunk_start);
+            chunk_filter.to_block = BlockId::Number(chunk_end);
+
+            logs.extend(
+                self.client
+                    .logs(chunk_filter)
+                    .into_iter()
+                    .map(From::from)
+                    .collect::<Vec<Log>>(),
+            );
+
+            // short-circuit once the caller's limit is satisfied so a wide
+            // `fromBlock`/`toBlock` span doesn't keep scanning past it.
+            if let Some(limit) = filter.limit {
+                if logs.len() >= limit {
+                    break;
+                }
+            }
+
+            chunk_start = chunk_end + 1;
+        }
 
         let logs = limit_logs(logs, filter.limit);
 
@@ -467,23 +611,37 @@ impl Eth for EthClient {
     }
 
     fn send_raw_transaction(&self, raw: Bytes) -> Result<RpcH256> {
-        /*
-        Rlp::new(&raw.into_vec()).as_val()
-			.map_err(errors::rlp)
-			.and_then(|tx| SignedTransaction::new(tx).map_err(errors::transaction))
-			.and_then(|signed_transaction| {
-				FullDispatcher::dispatch_transaction(
-					&*self.client,
-					&*self.miner,
-					signed_transaction.into(),
-				)
-			})
-			.map(Into::into)
-        */
-        self.client
+        let signed = self.verifier
+            .verify(raw.as_ref())
+            .map_err(|e| errors::invalid_params("raw transaction", format!("{:?}", e)))?;
+        let sender = signed.sender();
+        let hash = signed.hash();
+        let current_nonce = self.client.nonce(&sender, BlockId::Latest).unwrap_or_default();
+
+        match self.pool.import(signed, current_nonce) {
+            Ok(_) => {}
+            // a duplicate admission is harmless; still forward to the runtime
+            // so retried submissions behave the way clients expect.
+            Err(pool::Error::AlreadyImported(_)) => {}
+            Err(e) => {
+                return Err(errors::invalid_params(
+                    "raw transaction",
+                    format!("{:?}", e),
+                ))
+            }
+        }
+
+        let result = self.client
             .send_raw_transaction(raw.into())
             .map(Into::into)
-            .map_err(errors::call)
+            .map_err(errors::call);
+
+        if result.is_err() {
+            self.pool.remove(&hash);
+            self.pool.penalize(&sender);
+        }
+
+        result
     }
 
     fn submit_transaction(&self, raw: Bytes) -> Result<RpcH256> {
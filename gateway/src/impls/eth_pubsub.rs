@@ -30,7 +30,7 @@ use jsonrpc_pubsub::SubscriptionId;
 use parity_rpc::v1::helpers::{errors, Subscribers};
 use parity_rpc::v1::metadata::Metadata;
 use parity_rpc::v1::traits::EthPubSub;
-use parity_rpc::v1::types::{pubsub, H256, H64, Log, RichHeader};
+use parity_rpc::v1::types::{pubsub, H256, H64, Log, RichHeader, SyncStatus};
 
 use ethcore::encoded;
 use ethcore::filter::Filter as EthFilter;
@@ -39,6 +39,8 @@ use parity_reactor::Remote;
 use parking_lot::RwLock;
 
 use client::{ChainNotify, Client};
+use notify::WebhookNotifier;
+use pool::{SenderNonceReady, TransactionQueue};
 
 type PubSubClient = Sink<pubsub::Result>;
 
@@ -47,23 +49,54 @@ pub struct EthPubSubClient {
     handler: Arc<ChainNotificationHandler>,
     heads_subscribers: Arc<RwLock<Subscribers<PubSubClient>>>,
     logs_subscribers: Arc<RwLock<Subscribers<(PubSubClient, EthFilter)>>>,
+    pending_transactions_subscribers: Arc<RwLock<Subscribers<PubSubClient>>>,
+    syncing_subscribers: Arc<RwLock<Subscribers<PubSubClient>>>,
 }
 
 impl EthPubSubClient {
-    /// Creates new `EthPubSubClient`.
-    pub fn new(client: Arc<Client>, remote: Remote) -> Self {
+    /// Creates new `EthPubSubClient`, wiring it up to be notified whenever
+    /// `pool` admits a transaction.
+    pub fn new(client: Arc<Client>, remote: Remote, pool: Arc<TransactionQueue>) -> Self {
+        EthPubSubClient::with_webhooks(client, remote, pool, None)
+    }
+
+    /// Creates a new `EthPubSubClient` that also mirrors notifications to
+    /// `webhooks`, if given, as a parallel delivery path for operators who
+    /// don't want to hold a websocket open.
+    pub fn with_webhooks(
+        client: Arc<Client>,
+        remote: Remote,
+        pool: Arc<TransactionQueue>,
+        webhooks: Option<Arc<WebhookNotifier>>,
+    ) -> Self {
         let heads_subscribers = Arc::new(RwLock::new(Subscribers::default()));
         let logs_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let pending_transactions_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let syncing_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+
+        let handler = Arc::new(ChainNotificationHandler {
+            client,
+            remote,
+            pool: pool.clone(),
+            heads_subscribers: heads_subscribers.clone(),
+            logs_subscribers: logs_subscribers.clone(),
+            pending_transactions_subscribers: pending_transactions_subscribers.clone(),
+            syncing_subscribers: syncing_subscribers.clone(),
+            last_syncing_status: RwLock::new(None),
+            webhooks,
+        });
+
+        {
+            let handler = handler.clone();
+            pool.on_import(Box::new(move |hashes| handler.notify_new_transactions(hashes)));
+        }
 
         EthPubSubClient {
-            handler: Arc::new(ChainNotificationHandler {
-                client,
-                remote,
-                heads_subscribers: heads_subscribers.clone(),
-                logs_subscribers: logs_subscribers.clone(),
-            }),
+            handler,
             heads_subscribers,
             logs_subscribers,
+            pending_transactions_subscribers,
+            syncing_subscribers,
         }
     }
 
@@ -77,8 +110,68 @@ impl EthPubSubClient {
 pub struct ChainNotificationHandler {
     client: Arc<Client>,
     remote: Remote,
+    /// The pool backing `newPendingTransactions`, pruned of stale entries
+    /// on every new head (see `notify_heads`).
+    pool: Arc<TransactionQueue>,
     heads_subscribers: Arc<RwLock<Subscribers<PubSubClient>>>,
     logs_subscribers: Arc<RwLock<Subscribers<(PubSubClient, EthFilter)>>>,
+    pending_transactions_subscribers: Arc<RwLock<Subscribers<PubSubClient>>>,
+    syncing_subscribers: Arc<RwLock<Subscribers<PubSubClient>>>,
+    /// Last status pushed to `syncing_subscribers`, so idle synced nodes
+    /// don't re-send an unchanged payload on every head.
+    last_syncing_status: RwLock<Option<SyncStatus>>,
+    /// Optional parallel delivery path to operator-configured HTTP endpoints.
+    webhooks: Option<Arc<WebhookNotifier>>,
+}
+
+impl ChainNotificationHandler {
+    /// Notifies `newPendingTransactions` subscribers of freshly admitted
+    /// transaction hashes.
+    pub fn notify_new_transactions(&self, hashes: &[H256]) {
+        for subscriber in self.pending_transactions_subscribers.read().values() {
+            for &hash in hashes {
+                Self::notify(
+                    &self.remote,
+                    subscriber,
+                    pubsub::Result::TransactionHash(hash),
+                );
+            }
+        }
+
+        if let Some(ref webhooks) = self.webhooks {
+            webhooks.notify_new_transactions(hashes);
+        }
+    }
+
+    /// Current sync status. This gateway forwards transactions straight to
+    /// the Oasis runtime rather than importing blocks over devp2p, so there
+    /// is no "behind the chain head" state to report; kept as a method
+    /// (rather than a constant) so catching up becomes meaningful without
+    /// reshaping the subscription plumbing if that changes.
+    fn current_syncing_status(&self) -> SyncStatus {
+        SyncStatus::None
+    }
+
+    /// Samples the current sync status and, if it differs from the last
+    /// one sent, pushes it to `syncing_subscribers`.
+    fn notify_syncing(&self) {
+        if self.syncing_subscribers.read().is_empty() {
+            return;
+        }
+
+        let status = self.current_syncing_status();
+        {
+            let last = self.last_syncing_status.read();
+            if last.as_ref() == Some(&status) {
+                return;
+            }
+        }
+        *self.last_syncing_status.write() = Some(status.clone());
+
+        for subscriber in self.syncing_subscribers.read().values() {
+            Self::notify(&self.remote, subscriber, pubsub::Result::SyncState(status.clone()));
+        }
+    }
 }
 
 impl ChainNotificationHandler {
@@ -98,32 +191,84 @@ impl ChainNotify for ChainNotificationHandler {
     }
 
     fn notify_heads(&self, headers: &[encoded::Header]) {
-        for subscriber in self.heads_subscribers.read().values() {
-            for &ref header in headers {
-                // geth will fail to decode the response unless it has a number of
-                // fields even if they aren't relevant.
-                //
-                // See:
-                //  * https://github.com/ethereum/go-ethereum/issues/3230
-                //  * https://github.com/paritytech/parity-ethereum/issues/8841
-                let mut extra_info: BTreeMap<String, String> = BTreeMap::new();
-                extra_info.insert("mixHash".to_string(), format!("0x{:?}", H256::default()));
-                extra_info.insert("nonce".to_string(), format!("0x{:?}", H64::default()));
+        self.notify_syncing();
 
-                Self::notify(
-                    &self.remote,
-                    subscriber,
-                    pubsub::Result::Header(RichHeader {
-                        inner: header.into(),
-                        extra_info,
-                    }),
-                );
+        // prune nonces that fell behind as part of importing these blocks,
+        // so a mined transaction doesn't permanently hide later-nonce ones
+        // from the same sender in `pending()`/`ready_count_for_sender()`.
+        self.pool.cull(SenderNonceReady::new(&self.client));
+
+        // geth will fail to decode the response unless it has a number of
+        // fields even if they aren't relevant.
+        //
+        // See:
+        //  * https://github.com/ethereum/go-ethereum/issues/3230
+        //  * https://github.com/paritytech/parity-ethereum/issues/8841
+        let mut extra_info: BTreeMap<String, String> = BTreeMap::new();
+        extra_info.insert("mixHash".to_string(), format!("0x{:?}", H256::default()));
+        extra_info.insert("nonce".to_string(), format!("0x{:?}", H64::default()));
+
+        let rich_headers: Vec<RichHeader> = headers
+            .iter()
+            .map(|header| RichHeader {
+                inner: header.into(),
+                extra_info: extra_info.clone(),
+            })
+            .collect();
+
+        for subscriber in self.heads_subscribers.read().values() {
+            for header in &rich_headers {
+                Self::notify(&self.remote, subscriber, pubsub::Result::Header(header.clone()));
             }
         }
+
+        if let Some(ref webhooks) = self.webhooks {
+            webhooks.notify_heads(&rich_headers);
+        }
     }
 
-    fn notify_logs(&self, from_block: BlockId, to_block: BlockId) {
+    // `ChainNotify`'s definition and the block-import code that calls
+    // `notify_logs` both live outside this snapshot (there's no `client.rs`
+    // in this tree, only `use client::{ChainNotify, Client};`), same as the
+    // parity/parity_pubsub registration points chunk0-3/chunk1-5 touch. The
+    // trait and its caller need a matching `retracted` parameter added
+    // wherever they're actually defined before reorg removals reach here.
+    fn notify_logs(
+        &self,
+        from_block: BlockId,
+        to_block: BlockId,
+        retracted: Option<(BlockId, BlockId)>,
+    ) {
         for &(ref subscriber, ref filter) in self.logs_subscribers.read().values() {
+            // Removals for orphaned (retracted) blocks are delivered before
+            // additions for the newly-canonical range, matching geth/parity
+            // reorg semantics.
+            let removed_logs = retracted.map(|(retracted_from, retracted_to)| {
+                let mut removed_filter = filter.clone();
+
+                if removed_filter.from_block == BlockId::Latest {
+                    removed_filter.from_block = retracted_from;
+                }
+                if removed_filter.to_block == BlockId::Latest {
+                    removed_filter.to_block = retracted_to;
+                }
+
+                removed_filter.from_block = self.client
+                    .max_block_number(removed_filter.from_block, retracted_from);
+                removed_filter.to_block = self.client
+                    .min_block_number(removed_filter.to_block, retracted_to);
+
+                self.client
+                    .logs(removed_filter)
+                    .into_iter()
+                    .map(|entry| {
+                        let mut log: Log = entry.into();
+                        log.removed = true;
+                        log
+                    })
+                    .collect::<Vec<Log>>()
+            });
+
             let mut filter = filter.clone();
 
             // if filter.from_block == "Latest", replace with from_block
@@ -142,6 +287,10 @@ impl ChainNotify for ChainNotificationHandler {
             let remote = self.remote.clone();
             let subscriber = subscriber.clone();
             self.remote.spawn({
+                for log in removed_logs.into_iter().flatten() {
+                    Self::notify(&remote, &subscriber, pubsub::Result::Log(log))
+                }
+
                 let logs = self.client
                     .logs(filter)
                     .into_iter()
@@ -153,6 +302,19 @@ impl ChainNotify for ChainNotificationHandler {
                 Ok(())
             });
         }
+
+        if let Some(ref webhooks) = self.webhooks {
+            let logs = self.client
+                .logs(EthFilter {
+                    from_block,
+                    to_block,
+                    ..Default::default()
+                })
+                .into_iter()
+                .map(From::from)
+                .collect::<Vec<Log>>();
+            webhooks.notify_logs(&logs);
+        }
     }
 }
 
@@ -187,12 +349,19 @@ impl EthPubSub for EthPubSubClient {
             }
             (pubsub::Kind::Logs, _) => errors::invalid_params("logs", "Expected a filter object."),
             (pubsub::Kind::NewPendingTransactions, None) => {
-                // this is a no-op: we're not mining, so we have no pending transactions
+                self.pending_transactions_subscribers.write().push(subscriber);
                 return;
             }
             (pubsub::Kind::NewPendingTransactions, _) => {
                 errors::invalid_params("newPendingTransactions", "Expected no parameters.")
             }
+            (pubsub::Kind::Syncing, None) => {
+                self.syncing_subscribers.write().push(subscriber);
+                return;
+            }
+            (pubsub::Kind::Syncing, _) => {
+                errors::invalid_params("syncing", "Expected no parameters.")
+            }
             _ => errors::unimplemented(None),
         };
 
@@ -204,7 +373,12 @@ impl EthPubSub for EthPubSubClient {
         info!("unsubscribe(id: {:?})", id);
         let res = self.heads_subscribers.write().remove(&id).is_some();
         let res2 = self.logs_subscribers.write().remove(&id).is_some();
+        let res3 = self.pending_transactions_subscribers
+            .write()
+            .remove(&id)
+            .is_some();
+        let res4 = self.syncing_subscribers.write().remove(&id).is_some();
 
-        Ok(res || res2)
+        Ok(res || res2 || res3 || res4)
     }
 }
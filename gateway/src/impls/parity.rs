@@ -0,0 +1,124 @@
+// Copyright 2015-2018 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Parity rpc implementation.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use ethcore::client::BlockChainClient;
+
+use jsonrpc_core::{BoxFuture, MetaIoHandler, Result};
+use jsonrpc_macros::Trailing;
+
+use parity_rpc::v1::helpers::errors;
+use parity_rpc::v1::metadata::Metadata;
+use parity_rpc::v1::traits::Parity;
+use parity_rpc::v1::types::{block_number_to_id, BlockNumber, Header, PeerInfo, Peers,
+                            PendingTransaction as RpcPendingTransaction, Transaction,
+                            TransactionStats};
+
+use client::Client;
+use impls::eth::EthClient;
+use pool::{Ready, SenderNonceReady, TransactionQueue};
+
+/// Parity rpc implementation, providing the subset of the `parity_*`
+/// namespace that Oasis tooling expects on top of the standard `Eth` API.
+pub struct ParityClient {
+    client: Arc<Client>,
+    pool: Arc<TransactionQueue>,
+    enode: String,
+}
+
+impl ParityClient {
+    /// Creates new `ParityClient`.
+    pub fn new(client: Arc<Client>, pool: Arc<TransactionQueue>, enode: String) -> Self {
+        ParityClient {
+            client,
+            pool,
+            enode,
+        }
+    }
+
+    fn ready(&self) -> SenderNonceReady<Arc<Client>> {
+        SenderNonceReady::new(&self.client)
+    }
+}
+
+impl Parity for ParityClient {
+    type Metadata = Metadata;
+
+    fn pending_transactions(&self, limit: Trailing<usize>) -> Result<Vec<Transaction>> {
+        let pending_block = self.client.best_block_number() + 1;
+        let mut transactions: Vec<Transaction> = self.pool
+            .pending(self.ready())
+            .into_iter()
+            .map(|tx| EthClient::transaction_from_pending(tx, pending_block))
+            .collect();
+
+        if let Some(limit) = limit.into() {
+            transactions.truncate(limit);
+        }
+
+        Ok(transactions)
+    }
+
+    fn pending_transactions_stats(&self) -> Result<BTreeMap<String, TransactionStats>> {
+        // Single-gateway deployment: there are no peers to propagate to, so
+        // no per-transaction stats are reported (an empty map keeps the
+        // shape clients expect without claiming peer information we don't
+        // have).
+        Ok(BTreeMap::new())
+    }
+
+    fn block_header(&self, number: Trailing<BlockNumber>) -> BoxFuture<Header> {
+        let number = number.unwrap_or_default();
+        let id = block_number_to_id(number);
+
+        let result = self.client
+            .block_header(id)
+            .ok_or_else(errors::unknown_block)
+            .and_then(|header| header.decode().map_err(errors::decode))
+            .map(|header| Header {
+                inner: header.into(),
+                extra_info: BTreeMap::new(),
+            });
+
+        Box::new(::jsonrpc_core::futures::future::done(result))
+    }
+
+    fn enode(&self) -> Result<String> {
+        Ok(self.enode.clone())
+    }
+
+    fn net_peers(&self) -> Result<Peers> {
+        // The gateway does not itself participate in devp2p: it forwards to
+        // the Oasis runtime, so there are no Ethereum-level peers to report.
+        Ok(Peers {
+            active: 0,
+            connected: 0,
+            max: 0,
+            peers: Vec::<PeerInfo>::new(),
+        })
+    }
+}
+
+/// Registers `client` on `io` alongside the existing `Eth`/`EthPubSub`
+/// handlers, so the `parity_*` methods above become reachable. `start`
+/// should call this the same way it already registers `EthClient`.
+pub fn register(io: &mut MetaIoHandler<Metadata>, client: ParityClient) {
+    io.extend_with(client.to_delegate());
+}
@@ -0,0 +1,165 @@
+// Copyright 2015-2018 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+// Modeled after ethcore's `WorkPoster`/work-notifier design.
+
+//! Outbound HTTP webhook notifier.
+//!
+//! A parallel delivery path to the websocket pub-sub subscribers: operators
+//! register a list of URLs that receive the same new-heads/logs/pending-tx
+//! payloads as a POSTed JSON body, without having to hold a websocket open.
+
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ethereum_types::H256;
+use hyper::{Client as HttpClient, Request};
+use jsonrpc_core::futures::Future;
+use parity_rpc::v1::types::{Log, RichHeader};
+use parity_reactor::Remote;
+use serde::Serialize;
+use serde_json;
+use tokio_timer::Timer;
+
+const MAX_RETRIES: usize = 3;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Number of consecutive delivery failures (each exhausting its own
+/// `MAX_RETRIES`) after which a URL is considered persistently unreachable
+/// and skipped, so one dead endpoint doesn't keep paying the same retry
+/// cost on every single notification.
+const MAX_CONSECUTIVE_FAILURES: usize = 10;
+
+/// Posts new heads, matching logs, and pending-transaction hashes to a set
+/// of configured HTTP endpoints, mirroring `ChainNotificationHandler`'s
+/// websocket pub-sub streams.
+pub struct WebhookNotifier {
+    urls: Vec<String>,
+    remote: Remote,
+    http: HttpClient,
+    failures: Vec<Arc<AtomicUsize>>,
+}
+
+impl WebhookNotifier {
+    /// Creates a notifier that POSTs to each of `urls` whenever `notify_*`
+    /// is called, dispatching requests on `remote` so a slow or unreachable
+    /// endpoint never blocks block import.
+    pub fn new(urls: Vec<String>, remote: Remote) -> Arc<Self> {
+        let failures = urls.iter().map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        Arc::new(WebhookNotifier {
+            urls,
+            remote,
+            http: HttpClient::new(),
+            failures,
+        })
+    }
+
+    /// Posts a new block header to every configured URL.
+    pub fn notify_heads(&self, headers: &[RichHeader]) {
+        for header in headers {
+            self.broadcast("newHeads", header);
+        }
+    }
+
+    /// Posts matching logs to every configured URL.
+    pub fn notify_logs(&self, logs: &[Log]) {
+        for log in logs {
+            self.broadcast("logs", log);
+        }
+    }
+
+    /// Posts freshly admitted transaction hashes to every configured URL.
+    pub fn notify_new_transactions(&self, hashes: &[H256]) {
+        for hash in hashes {
+            self.broadcast("newPendingTransactions", hash);
+        }
+    }
+
+    fn broadcast<T: Serialize>(&self, kind: &str, payload: &T) {
+        let body = match serde_json::to_vec(&WebhookPayload { kind, payload }) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!(target: "rpc", "failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+
+        for (index, url) in self.urls.iter().enumerate() {
+            if self.failures[index].load(AtomicOrdering::Relaxed) >= MAX_CONSECUTIVE_FAILURES {
+                warn!(target: "rpc", "skipping webhook {}: persistently failing", url);
+                continue;
+            }
+            self.post_with_retry(index, url.clone(), body.clone());
+        }
+    }
+
+    fn post_with_retry(&self, index: usize, url: String, body: Vec<u8>) {
+        let http = self.http.clone();
+        let failures = self.failures[index].clone();
+        let attempt = 0;
+        self.remote
+            .spawn(move |_| Self::attempt(http, url, body, attempt, failures));
+    }
+
+    fn attempt(
+        http: HttpClient,
+        url: String,
+        body: Vec<u8>,
+        attempt: usize,
+        failures: Arc<AtomicUsize>,
+    ) -> Box<Future<Item = (), Error = ()> + Send> {
+        let request = match Request::post(&url).body(body.clone().into()) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!(target: "rpc", "invalid webhook url {}: {}", url, e);
+                return Box::new(::jsonrpc_core::futures::future::ok(()));
+            }
+        };
+
+        Box::new(
+            http.request(request)
+                .then(move |result| -> Box<Future<Item = (), Error = ()> + Send> {
+                    match result {
+                        Ok(_) => {
+                            failures.store(0, AtomicOrdering::Relaxed);
+                        }
+                        Err(e) => {
+                            warn!(target: "rpc", "webhook delivery to {} failed: {}", url, e);
+                            if attempt < MAX_RETRIES {
+                                let backoff = INITIAL_BACKOFF * 2u32.pow(attempt as u32);
+                                // Delay the retry with a timer future rather than
+                                // thread::sleep, so a slow/unreachable endpoint
+                                // never blocks the shared reactor thread.
+                                return Box::new(Timer::default().sleep(backoff).then(move |_| {
+                                    Self::attempt(http, url, body, attempt + 1, failures)
+                                }));
+                            }
+                            error!(target: "rpc", "webhook {} dropped after {} attempts", url, attempt + 1);
+                            failures.fetch_add(1, AtomicOrdering::Relaxed);
+                        }
+                    }
+                    Box::new(::jsonrpc_core::futures::future::ok(()))
+                }),
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a, T: Serialize> {
+    kind: &'a str,
+    payload: &'a T,
+}
@@ -0,0 +1,512 @@
+// Copyright 2015-2018 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+// Adapted from OpenEthereum's `miner/src/pool` redesign.
+
+//! Local transaction pool.
+//!
+//! Sits between the RPC layer and the runtime: incoming raw transactions are
+//! admitted through a pluggable `Verifier`, scored and ordered per-sender by
+//! nonce, and surfaced to the RPC layer through a pluggable `Ready` predicate
+//! that distinguishes transactions that are immediately runnable ("ready")
+//! from ones with a nonce gap ahead of them ("future").
+
+pub mod ready;
+pub mod scoring;
+pub mod verifier;
+
+pub use self::ready::{Readiness, Ready};
+pub use self::scoring::Scoring;
+pub use self::verifier::{Verifier, VerifierError};
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Arc;
+
+use ethereum_types::{Address, H256, U256};
+use parking_lot::RwLock;
+use transaction::SignedTransaction;
+
+use client::Client;
+use ethcore::client::{BlockChainClient, BlockId, StateOrBlock};
+
+/// Abstraction over the chain state the pool needs in order to verify and
+/// order transactions, so the pool itself stays independent of `client::Client`.
+pub trait PoolClient: Send + Sync {
+    /// Current nonce of `sender`, as seen by the latest imported block.
+    fn latest_nonce(&self, sender: &Address) -> U256;
+    /// Current balance of `sender`, as seen by the latest imported block.
+    fn latest_balance(&self, sender: &Address) -> U256;
+}
+
+impl PoolClient for Client {
+    fn latest_nonce(&self, sender: &Address) -> U256 {
+        self.nonce(sender, BlockId::Latest).unwrap_or_default()
+    }
+
+    fn latest_balance(&self, sender: &Address) -> U256 {
+        let state: StateOrBlock = BlockId::Latest.into();
+        self.balance(sender, state).unwrap_or_default()
+    }
+}
+
+impl<T: PoolClient + ?Sized> PoolClient for Arc<T> {
+    fn latest_nonce(&self, sender: &Address) -> U256 {
+        (**self).latest_nonce(sender)
+    }
+
+    fn latest_balance(&self, sender: &Address) -> U256 {
+        (**self).latest_balance(sender)
+    }
+}
+
+/// A transaction that has passed verification and is tracked by the pool.
+#[derive(Debug, Clone)]
+pub struct VerifiedTransaction {
+    /// The underlying signed transaction.
+    pub transaction: SignedTransaction,
+    /// Cached transaction hash.
+    pub hash: H256,
+    /// Cached sender address.
+    pub sender: Address,
+    /// Insertion order, used as a score tie-break and for stable iteration.
+    pub insertion_id: u64,
+    /// Current score: set by `Scoring::initial_score` on admission and
+    /// halved in place by penalization.
+    pub score: RwLock<U256>,
+}
+
+impl VerifiedTransaction {
+    /// Transaction nonce.
+    pub fn nonce(&self) -> U256 {
+        self.transaction.nonce
+    }
+
+    /// Effective gas price of the transaction.
+    pub fn gas_price(&self) -> U256 {
+        self.transaction.gas_price
+    }
+}
+
+/// Errors returned when importing a transaction into the pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A transaction with the same hash is already in the pool.
+    AlreadyImported(H256),
+    /// The pool is full and this transaction did not score high enough to
+    /// evict anything.
+    TooCheapToEnter,
+    /// The sender already has `max_per_sender_future` non-contiguous
+    /// transactions queued.
+    TooManyFutureTransactions,
+    /// Verification rejected the transaction before it reached the queue.
+    Verification(VerifierError),
+}
+
+/// Tunables for `TransactionQueue`.
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Maximum number of transactions held across all senders.
+    pub max_count: usize,
+    /// Maximum number of nonce-gapped ("future") transactions a single
+    /// sender may have queued at once.
+    pub max_per_sender_future: usize,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_count: 8_192,
+            max_per_sender_future: 16,
+        }
+    }
+}
+
+/// Per-sender transactions, ordered by nonce.
+#[derive(Default)]
+struct SenderQueue {
+    by_nonce: BTreeMap<U256, Arc<VerifiedTransaction>>,
+}
+
+/// The transaction pool: verification, per-sender nonce ordering, scoring
+/// and eviction all live here. Readiness (what's currently runnable) is
+/// computed on demand via a caller-supplied `Ready` predicate, since that
+/// depends on up-to-date chain state the pool does not itself track.
+pub struct TransactionQueue<S: Scoring = scoring::GasPriceScoring> {
+    senders: RwLock<HashMap<Address, SenderQueue>>,
+    by_hash: RwLock<HashMap<H256, Arc<VerifiedTransaction>>>,
+    config: PoolConfig,
+    scoring: S,
+    next_insertion_id: AtomicUsize,
+    /// Callbacks invoked with the hash of every transaction admitted to the
+    /// queue, e.g. to drive the `newPendingTransactions` subscription.
+    import_listeners: RwLock<Vec<Box<Fn(&[H256]) + Send + Sync>>>,
+}
+
+impl<S: Scoring> TransactionQueue<S> {
+    /// Creates a new, empty queue.
+    pub fn new(config: PoolConfig, scoring: S) -> Self {
+        TransactionQueue {
+            senders: RwLock::new(HashMap::new()),
+            by_hash: RwLock::new(HashMap::new()),
+            config,
+            scoring,
+            next_insertion_id: AtomicUsize::new(0),
+            import_listeners: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Registers a callback to be invoked with the hash of every
+    /// transaction admitted to the queue.
+    pub fn on_import(&self, callback: Box<Fn(&[H256]) + Send + Sync>) {
+        self.import_listeners.write().push(callback);
+    }
+
+    /// Number of transactions currently held.
+    pub fn len(&self) -> usize {
+        self.by_hash.read().len()
+    }
+
+    /// Whether the pool is at capacity.
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.config.max_count
+    }
+
+    /// Looks up a transaction by hash.
+    pub fn by_hash(&self, hash: &H256) -> Option<Arc<VerifiedTransaction>> {
+        self.by_hash.read().get(hash).cloned()
+    }
+
+    /// Imports an already-verified transaction.
+    ///
+    /// `current_nonce` is the sender's current on-chain nonce, used only to
+    /// decide whether the transaction counts against the sender's
+    /// future-nonce cap (it does not affect ordering, which is always by
+    /// raw nonce).
+    pub fn import(
+        &self,
+        transaction: SignedTransaction,
+        current_nonce: U256,
+    ) -> Result<H256, Error> {
+        let hash = transaction.hash();
+        if self.by_hash.read().contains_key(&hash) {
+            return Err(Error::AlreadyImported(hash));
+        }
+
+        let sender = transaction.sender();
+        let nonce = transaction.nonce;
+
+        if nonce > current_nonce {
+            let senders = self.senders.read();
+            if let Some(queue) = senders.get(&sender) {
+                // transactions contiguous with `current_nonce` are ready, not
+                // future: only those past the first nonce gap count.
+                let mut next_nonce = current_nonce;
+                while queue.by_nonce.contains_key(&next_nonce) {
+                    next_nonce = next_nonce + U256::one();
+                }
+                if nonce > next_nonce {
+                    let future_count = queue
+                        .by_nonce
+                        .keys()
+                        .filter(|n| **n > next_nonce)
+                        .count();
+                    if future_count >= self.config.max_per_sender_future {
+                        return Err(Error::TooManyFutureTransactions);
+                    }
+                }
+            }
+        }
+
+        if self.is_full() {
+            match self.evict_lowest_scored(&transaction) {
+                Some(()) => {}
+                None => return Err(Error::TooCheapToEnter),
+            }
+        }
+
+        let insertion_id = self.next_insertion_id.fetch_add(1, AtomicOrdering::Relaxed) as u64;
+        let score = self.scoring.initial_score(&transaction);
+        let verified = Arc::new(VerifiedTransaction {
+            transaction,
+            hash,
+            sender,
+            insertion_id,
+            score: RwLock::new(score),
+        });
+
+        self.senders
+            .write()
+            .entry(sender)
+            .or_insert_with(SenderQueue::default)
+            .by_nonce
+            .insert(nonce, verified.clone());
+        self.by_hash.write().insert(hash, verified);
+
+        for listener in self.import_listeners.read().iter() {
+            listener(&[hash]);
+        }
+
+        Ok(hash)
+    }
+
+    /// Removes a transaction from the pool, e.g. once it has been included
+    /// in a block or has failed.
+    pub fn remove(&self, hash: &H256) -> Option<Arc<VerifiedTransaction>> {
+        let removed = self.by_hash.write().remove(hash)?;
+        let mut senders = self.senders.write();
+        if let Some(queue) = senders.get_mut(&removed.sender) {
+            queue.by_nonce.remove(&removed.nonce());
+            if queue.by_nonce.is_empty() {
+                senders.remove(&removed.sender);
+            }
+        }
+        Some(removed)
+    }
+
+    /// Penalizes a sender after one of their transactions failed, halving
+    /// the score of everything else they still have queued so that
+    /// spammers are the first to be evicted under pressure.
+    pub fn penalize(&self, sender: &Address) {
+        let senders = self.senders.read();
+        if let Some(queue) = senders.get(sender) {
+            for tx in queue.by_nonce.values() {
+                let mut score = tx.score.write();
+                *score = *score / 2;
+            }
+        }
+    }
+
+    /// Returns the transactions that are "ready" to run, in scored order,
+    /// per the given `Ready` predicate. A transaction is never placed ahead
+    /// of a lower-nonce one from the same sender. Nonce-gapped transactions
+    /// for a sender are never returned even if a later-nonce transaction
+    /// would otherwise score highly, since they cannot execute yet.
+    pub fn pending<R: Ready>(&self, mut ready: R) -> Vec<Arc<VerifiedTransaction>> {
+        let senders = self.senders.read();
+        let mut queues: Vec<VecDeque<Arc<VerifiedTransaction>>> = Vec::new();
+        for queue in senders.values() {
+            let mut ready_txs = VecDeque::new();
+            for tx in queue.by_nonce.values() {
+                match ready.is_ready(tx) {
+                    Readiness::Ready => ready_txs.push_back(tx.clone()),
+                    Readiness::Future | Readiness::Stale => break,
+                }
+            }
+            queues.push(ready_txs);
+        }
+
+        // repeatedly take the best-scored head among the senders' queues.
+        let mut result = Vec::new();
+        while let Some(best) = (0..queues.len())
+            .filter(|&i| !queues[i].is_empty())
+            .min_by(|&i, &j| self.scoring.compare(&queues[i][0], &queues[j][0]))
+        {
+            result.extend(queues[best].pop_front());
+        }
+        result
+    }
+
+    /// Number of transactions ready to run for `sender`, per `ready`.
+    pub fn ready_count_for_sender<R: Ready>(&self, sender: &Address, mut ready: R) -> usize {
+        let senders = self.senders.read();
+        match senders.get(sender) {
+            Some(queue) => queue
+                .by_nonce
+                .values()
+                .take_while(|tx| ready.is_ready(tx) == Readiness::Ready)
+                .count(),
+            None => 0,
+        }
+    }
+
+    /// Removes transactions that can never execute because their nonce has
+    /// fallen behind the sender's current on-chain nonce — typically
+    /// because another transaction with that nonce was just mined. Without
+    /// this, a single mined or superseded entry would sit at the front of
+    /// its sender's nonce-ordered queue forever, since `pending()` and
+    /// `ready_count_for_sender()` both stop at the first non-`Ready`
+    /// transaction they see. Should be called after every new block.
+    pub fn cull<R: Ready>(&self, mut ready: R) -> Vec<H256> {
+        let stale: Vec<H256> = {
+            let senders = self.senders.read();
+            let mut stale = Vec::new();
+            for queue in senders.values() {
+                for tx in queue.by_nonce.values() {
+                    if ready.is_ready(tx) == Readiness::Stale {
+                        stale.push(tx.hash);
+                    }
+                }
+            }
+            stale
+        };
+
+        for hash in &stale {
+            self.remove(hash);
+        }
+
+        stale
+    }
+
+    /// Evicts the single lowest-scored transaction in the pool, provided
+    /// `candidate` scores higher than it. Returns `None` (evicting nothing)
+    /// if `candidate` is the worst transaction around, i.e. too cheap to
+    /// bother admitting.
+    fn evict_lowest_scored(&self, candidate: &SignedTransaction) -> Option<()> {
+        let candidate_score = self.scoring.initial_score(candidate);
+        let by_hash = self.by_hash.read();
+        let worst = by_hash
+            .values()
+            .min_by(|a, b| (*a.score.read()).cmp(&*b.score.read()))
+            .cloned();
+        drop(by_hash);
+
+        match worst {
+            Some(worst) if *worst.score.read() < candidate_score => {
+                let hash = worst.hash;
+                drop(worst);
+                self.remove(&hash);
+                Some(())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethkey::{Generator, KeyPair, Random};
+    use transaction::{Action, Transaction};
+
+    fn signed(nonce: u64, gas_price: u64, keypair: &KeyPair) -> SignedTransaction {
+        Transaction {
+            action: Action::Create,
+            nonce: U256::from(nonce),
+            gas_price: U256::from(gas_price),
+            gas: U256::from(100_000),
+            value: U256::zero(),
+            data: Vec::new(),
+        }.sign(keypair.secret(), None)
+    }
+
+    struct FixedClient {
+        nonce: U256,
+        balance: U256,
+    }
+
+    impl PoolClient for FixedClient {
+        fn latest_nonce(&self, _sender: &Address) -> U256 {
+            self.nonce
+        }
+
+        fn latest_balance(&self, _sender: &Address) -> U256 {
+            self.balance
+        }
+    }
+
+    fn queue() -> TransactionQueue {
+        TransactionQueue::new(PoolConfig::default(), scoring::GasPriceScoring::default())
+    }
+
+    #[test]
+    fn pending_keeps_sender_nonce_order_over_gas_price() {
+        let pool = queue();
+        let keypair = Random.generate().unwrap();
+
+        pool.import(signed(0, 10, &keypair), U256::zero()).unwrap();
+        pool.import(signed(1, 50, &keypair), U256::zero()).unwrap();
+
+        let client = FixedClient {
+            nonce: U256::zero(),
+            balance: U256::from(u64::max_value()),
+        };
+        let pending = pool.pending(ready::SenderNonceReady::new(&client));
+
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].nonce(), U256::zero());
+        assert_eq!(pending[1].nonce(), U256::one());
+    }
+
+    #[test]
+    fn future_nonce_gap_hides_later_transactions() {
+        let pool = queue();
+        let keypair = Random.generate().unwrap();
+
+        // nonce 1 with nothing at nonce 0 yet: a gap ahead of the sender's
+        // current nonce, so it must not show up as ready.
+        pool.import(signed(1, 10, &keypair), U256::zero()).unwrap();
+
+        let client = FixedClient {
+            nonce: U256::zero(),
+            balance: U256::from(u64::max_value()),
+        };
+        let pending = pool.pending(ready::SenderNonceReady::new(&client));
+
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn cull_removes_stale_transactions_so_later_nonces_become_visible() {
+        let pool = queue();
+        let keypair = Random.generate().unwrap();
+
+        pool.import(signed(0, 10, &keypair), U256::zero()).unwrap();
+        pool.import(signed(1, 10, &keypair), U256::zero()).unwrap();
+
+        // the chain advanced past nonce 0 (e.g. it was mined), so the
+        // sender's current nonce is now 1.
+        let client = FixedClient {
+            nonce: U256::one(),
+            balance: U256::from(u64::max_value()),
+        };
+
+        assert!(
+            pool.pending(ready::SenderNonceReady::new(&client))
+                .is_empty(),
+            "nonce-0 entry is stale and must not be reported as ready"
+        );
+
+        let removed = pool.cull(ready::SenderNonceReady::new(&client));
+        assert_eq!(removed.len(), 1);
+
+        let pending = pool.pending(ready::SenderNonceReady::new(&client));
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].nonce(), U256::one());
+    }
+
+    #[test]
+    fn import_evicts_lowest_scored_when_full() {
+        let pool = TransactionQueue::new(
+            PoolConfig {
+                max_count: 1,
+                max_per_sender_future: 16,
+            },
+            scoring::GasPriceScoring::default(),
+        );
+        let cheap = Random.generate().unwrap();
+        let rich = Random.generate().unwrap();
+
+        pool.import(signed(0, 1, &cheap), U256::zero()).unwrap();
+        assert_eq!(pool.len(), 1);
+
+        pool.import(signed(0, 100, &rich), U256::zero()).unwrap();
+        assert_eq!(pool.len(), 1, "higher-scored transaction should evict the cheap one");
+
+        let by_hash = pool.by_hash(&signed(0, 100, &rich).hash());
+        assert!(by_hash.is_some());
+    }
+}
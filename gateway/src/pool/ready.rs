@@ -0,0 +1,81 @@
+// Copyright 2015-2018 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Classifies queued transactions as runnable now, runnable later, or dead.
+
+use std::collections::HashMap;
+
+use ethereum_types::{Address, U256};
+
+use super::{PoolClient, VerifiedTransaction};
+
+/// The outcome of checking a transaction against current chain state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Readiness {
+    /// Contiguous with the sender's current nonce (or a preceding ready
+    /// transaction already seen this pass): can execute next.
+    Ready,
+    /// There is a nonce gap ahead of this transaction; it cannot execute
+    /// until earlier ones land.
+    Future,
+    /// The nonce is behind the sender's current nonce; it can never
+    /// execute and should be dropped.
+    Stale,
+}
+
+/// Decides whether a queued transaction is currently runnable.
+pub trait Ready {
+    /// Classifies `transaction`. Implementations are expected to be called
+    /// in nonce order within a sender, tracking the "next expected nonce"
+    /// as they go.
+    fn is_ready(&mut self, transaction: &VerifiedTransaction) -> Readiness;
+}
+
+/// Default `Ready`: tracks, per sender, the next contiguous nonce expected
+/// starting from the account's current on-chain nonce.
+pub struct SenderNonceReady<'a, C: PoolClient + 'a> {
+    client: &'a C,
+    next_nonce: HashMap<Address, U256>,
+}
+
+impl<'a, C: PoolClient> SenderNonceReady<'a, C> {
+    /// Creates a new predicate backed by `client`.
+    pub fn new(client: &'a C) -> Self {
+        SenderNonceReady {
+            client,
+            next_nonce: HashMap::new(),
+        }
+    }
+}
+
+impl<'a, C: PoolClient> Ready for SenderNonceReady<'a, C> {
+    fn is_ready(&mut self, transaction: &VerifiedTransaction) -> Readiness {
+        let expected = *self
+            .next_nonce
+            .entry(transaction.sender)
+            .or_insert_with(|| self.client.latest_nonce(&transaction.sender));
+
+        let nonce = transaction.nonce();
+        if nonce < expected {
+            Readiness::Stale
+        } else if nonce == expected {
+            self.next_nonce.insert(transaction.sender, expected + 1);
+            Readiness::Ready
+        } else {
+            Readiness::Future
+        }
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright 2015-2018 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Orders transactions within the pool and decides which to evict first.
+
+use std::cmp::Ordering;
+
+use ethereum_types::U256;
+use transaction::SignedTransaction;
+
+use super::VerifiedTransaction;
+
+/// Assigns an initial score to newly-admitted transactions and orders
+/// already-admitted ones, e.g. for building the ready set or picking an
+/// eviction candidate.
+pub trait Scoring: Send + Sync {
+    /// Score assigned to `transaction` on admission.
+    fn initial_score(&self, transaction: &SignedTransaction) -> U256;
+
+    /// Orders two already-scored transactions, highest priority first.
+    fn compare(&self, a: &VerifiedTransaction, b: &VerifiedTransaction) -> Ordering;
+}
+
+/// Default scoring: order by effective gas price, tie-broken by insertion
+/// order (earlier transactions win, matching first-seen-first-served).
+#[derive(Default, Clone, Copy)]
+pub struct GasPriceScoring;
+
+impl Scoring for GasPriceScoring {
+    fn initial_score(&self, transaction: &SignedTransaction) -> U256 {
+        transaction.gas_price
+    }
+
+    fn compare(&self, a: &VerifiedTransaction, b: &VerifiedTransaction) -> Ordering {
+        (*b.score.read())
+            .cmp(&*a.score.read())
+            .then_with(|| a.insertion_id.cmp(&b.insertion_id))
+    }
+}
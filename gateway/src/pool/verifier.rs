@@ -0,0 +1,104 @@
+// Copyright 2015-2018 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Admission checks run on every transaction before it enters the pool.
+
+use ethereum_types::U256;
+use rlp::Rlp;
+use transaction::{SignedTransaction, UnverifiedTransaction};
+
+use super::PoolClient;
+
+/// Why a transaction was rejected before admission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifierError {
+    /// The raw bytes could not be RLP-decoded into a transaction.
+    Rlp(String),
+    /// The signature does not recover to a valid sender.
+    InvalidSignature,
+    /// `gas_limit` is below the transaction's intrinsic gas cost.
+    InsufficientGas,
+    /// The sender's balance cannot cover `gas_limit * gas_price + value`.
+    InsufficientBalance,
+    /// The nonce is lower than the sender's current on-chain nonce, so the
+    /// transaction could never execute.
+    Stale,
+}
+
+/// Checks a raw transaction is well-formed and admissible given current
+/// chain state, producing a `SignedTransaction` ready for the pool.
+pub trait Verifier {
+    /// Decode, recover the sender and run admission checks on `raw`.
+    fn verify(&self, raw: &[u8]) -> Result<SignedTransaction, VerifierError>;
+}
+
+/// Default `Verifier`: checks signature, intrinsic gas and sender
+/// balance/nonce against the given `PoolClient`.
+pub struct BasicVerifier<C: PoolClient> {
+    client: C,
+    /// Minimum gas a plain value transfer (no data) requires; real intrinsic
+    /// gas scales with calldata length.
+    base_intrinsic_gas: U256,
+}
+
+impl<C: PoolClient> BasicVerifier<C> {
+    /// Creates a new verifier backed by `client`.
+    pub fn new(client: C) -> Self {
+        BasicVerifier {
+            client,
+            base_intrinsic_gas: U256::from(21_000),
+        }
+    }
+
+    fn intrinsic_gas(&self, data: &[u8]) -> U256 {
+        let data_cost: U256 = data
+            .iter()
+            .map(|&b| if b == 0 { 4 } else { 68 })
+            .fold(U256::zero(), |acc, cost| acc + U256::from(cost));
+        self.base_intrinsic_gas + data_cost
+    }
+}
+
+impl<C: PoolClient> Verifier for BasicVerifier<C> {
+    fn verify(&self, raw: &[u8]) -> Result<SignedTransaction, VerifierError> {
+        let unverified: UnverifiedTransaction = Rlp::new(raw)
+            .as_val()
+            .map_err(|e| VerifierError::Rlp(format!("{}", e)))?;
+
+        let transaction =
+            SignedTransaction::new(unverified).map_err(|_| VerifierError::InvalidSignature)?;
+
+        if transaction.gas < self.intrinsic_gas(&transaction.data) {
+            return Err(VerifierError::InsufficientGas);
+        }
+
+        let sender = transaction.sender();
+        let current_nonce = self.client.latest_nonce(&sender);
+        if transaction.nonce < current_nonce {
+            return Err(VerifierError::Stale);
+        }
+
+        let cost = transaction
+            .gas
+            .saturating_mul(transaction.gas_price)
+            .saturating_add(transaction.value);
+        if self.client.latest_balance(&sender) < cost {
+            return Err(VerifierError::InsufficientBalance);
+        }
+
+        Ok(transaction)
+    }
+}
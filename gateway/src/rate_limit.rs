@@ -0,0 +1,363 @@
+// Copyright 2015-2018 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+// Borrows the flow-control model from `ethcore/light/src/net/request_credit.rs`.
+
+//! Per-connection request credit accounting.
+//!
+//! Each RPC method has a static `compute_cost`; every connection owns a
+//! credit bucket that refills linearly over time up to a cap, and requests
+//! whose cost exceeds the available balance are rejected with a "request
+//! throttled" error instead of being served.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use jsonrpc_core::futures::{future, Future};
+use jsonrpc_core::{
+    Call, Error, ErrorCode, Failure, Id, MetaIoHandler, Metadata, Middleware, Output, Params,
+    Version,
+};
+use parking_lot::Mutex;
+use serde_json::Value;
+
+/// Tunables for the credit bucket shared by a single connection.
+#[derive(Debug, Clone)]
+pub struct CreditLimitOptions {
+    /// Maximum credits a connection can accumulate.
+    pub max_credits: f64,
+    /// Credits granted per second.
+    pub recharge_rate: f64,
+}
+
+impl Default for CreditLimitOptions {
+    fn default() -> Self {
+        CreditLimitOptions {
+            max_credits: 1_000.0,
+            recharge_rate: 200.0,
+        }
+    }
+}
+
+/// Static cost table for RPC methods, in credits.
+#[derive(Debug, Clone)]
+pub struct MethodCosts {
+    default_cost: f64,
+    costs: HashMap<String, f64>,
+    /// Cost charged per block of range for range-scanning methods
+    /// (`eth_getLogs` and friends), in addition to their base cost.
+    pub per_block_cost: f64,
+}
+
+impl Default for MethodCosts {
+    fn default() -> Self {
+        let mut costs = HashMap::new();
+        costs.insert("eth_blockNumber".to_string(), 1.0);
+        costs.insert("eth_gasPrice".to_string(), 1.0);
+        costs.insert("eth_chainId".to_string(), 1.0);
+        costs.insert("eth_getBalance".to_string(), 5.0);
+        costs.insert("eth_getTransactionCount".to_string(), 5.0);
+        costs.insert("eth_sendRawTransaction".to_string(), 10.0);
+        costs.insert("eth_call".to_string(), 50.0);
+        costs.insert("eth_estimateGas".to_string(), 50.0);
+        costs.insert("eth_getLogs".to_string(), 20.0);
+
+        MethodCosts {
+            default_cost: 10.0,
+            costs,
+            per_block_cost: 0.5,
+        }
+    }
+}
+
+impl MethodCosts {
+    /// Base cost of calling `method`, before any range-dependent surcharge.
+    pub fn base_cost(&self, method: &str) -> f64 {
+        self.costs.get(method).cloned().unwrap_or(self.default_cost)
+    }
+
+    /// Cost of calling `method` against a block range spanning
+    /// `block_count` blocks (1 for methods that are not range-based).
+    pub fn cost_for_range(&self, method: &str, block_count: u64) -> f64 {
+        self.base_cost(method) + self.per_block_cost * (block_count.saturating_sub(1) as f64)
+    }
+}
+
+/// Number of blocks `call` would scan, for methods whose cost should scale
+/// with range width. Only methods that take a `{fromBlock, toBlock}` filter
+/// as their first parameter are range-based; everything else costs 1 block.
+/// Returns 1 (rather than guessing) whenever either bound is a tag like
+/// `"latest"`/`"pending"` instead of an explicit `0x`-prefixed number,
+/// since resolving those to block numbers would require chain-head state
+/// this middleware doesn't have.
+fn block_count(method: &str, params: &Params) -> u64 {
+    if method != "eth_getLogs" {
+        return 1;
+    }
+
+    let filter = match params {
+        Params::Array(args) => match args.get(0) {
+            Some(filter) => filter,
+            None => return 1,
+        },
+        _ => return 1,
+    };
+
+    let from = filter.get("fromBlock").and_then(parse_block_number);
+    let to = filter.get("toBlock").and_then(parse_block_number);
+
+    match (from, to) {
+        (Some(from), Some(to)) if to >= from => to - from + 1,
+        _ => 1,
+    }
+}
+
+fn parse_block_number(value: &Value) -> Option<u64> {
+    let s = value.as_str()?;
+    if !s.starts_with("0x") {
+        return None;
+    }
+    u64::from_str_radix(&s[2..], 16).ok()
+}
+
+/// A single connection's refilling credit balance.
+struct CreditBucket {
+    balance: f64,
+    last_refill: Instant,
+}
+
+impl CreditBucket {
+    fn new(options: &CreditLimitOptions) -> Self {
+        CreditBucket {
+            balance: options.max_credits,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, options: &CreditLimitOptions) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.balance = (self.balance + elapsed * options.recharge_rate).min(options.max_credits);
+        self.last_refill = now;
+    }
+}
+
+/// Tracks a credit bucket per connection and enforces per-request cost
+/// against it. Wraps the `Eth`/`parity` handlers as middleware rather than
+/// changing individual methods.
+pub struct RequestMeter {
+    options: CreditLimitOptions,
+    costs: MethodCosts,
+    buckets: Mutex<HashMap<String, CreditBucket>>,
+}
+
+impl RequestMeter {
+    /// Creates a new meter with the given limits and cost table.
+    pub fn new(options: CreditLimitOptions, costs: MethodCosts) -> Arc<Self> {
+        Arc::new(RequestMeter {
+            options,
+            costs,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Attempts to charge `connection_id` the cost of calling `method` over
+    /// `block_count` blocks (1 for non-range methods). Returns the JSON-RPC
+    /// "request throttled" error if there isn't enough credit.
+    pub fn check(
+        &self,
+        connection_id: &str,
+        method: &str,
+        block_count: u64,
+    ) -> Result<(), Error> {
+        let cost = self.costs.cost_for_range(method, block_count);
+
+        let mut buckets = self.buckets.lock();
+        let bucket = buckets
+            .entry(connection_id.to_string())
+            .or_insert_with(|| CreditBucket::new(&self.options));
+        bucket.refill(&self.options);
+
+        if bucket.balance < cost {
+            return Err(throttled_error());
+        }
+
+        bucket.balance -= cost;
+        Ok(())
+    }
+
+    /// Drops the bucket for a connection that has disconnected.
+    pub fn remove_connection(&self, connection_id: &str) {
+        self.buckets.lock().remove(connection_id);
+    }
+}
+
+/// Names the connection a request arrived on, so `RequestMeter` can meter
+/// each client against its own bucket instead of one shared global one.
+/// Implement this for whatever concrete `Metadata` type `start`'s
+/// `MetaExtractor` builds (e.g. by stashing the transport's peer address or
+/// session id in it, then returning that here).
+pub trait ConnectionId {
+    fn connection_id(&self) -> String;
+}
+
+/// Cross-cutting enforcement point: build the `MetaIoHandler` with
+/// `rate_limit::handler(..)` (instead of `MetaIoHandler::default()`) to
+/// charge every call against its connection's credit bucket before it
+/// reaches the `Eth`/`parity` handlers registered on it, instead of
+/// changing each method individually.
+impl<M: Metadata + ConnectionId> Middleware<M> for Arc<RequestMeter> {
+    type Future = future::FutureResult<Option<Output>, ()>;
+    type CallFuture = future::FutureResult<Option<Output>, ()>;
+
+    fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> future::Either<Self::Future, X>
+    where
+        F: FnOnce(Call, M) -> X + Send,
+        X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+    {
+        let (method, params, id) = match call {
+            Call::MethodCall(ref method_call) => (
+                Some(method_call.method.clone()),
+                method_call.params.clone(),
+                method_call.id.clone(),
+            ),
+            Call::Notification(ref notification) => (
+                Some(notification.method.clone()),
+                notification.params.clone(),
+                Id::Null,
+            ),
+            Call::Invalid { ref id } => (None, Params::None, id.clone()),
+        };
+
+        if let Some(ref method) = method {
+            let connection_id = meta.connection_id();
+            let block_count = block_count(method, &params);
+            if let Err(e) = self.check(&connection_id, method, block_count) {
+                let failure = Output::Failure(Failure {
+                    jsonrpc: Some(Version::V2),
+                    error: e,
+                    id,
+                });
+                return future::Either::A(future::ok(Some(failure)));
+            }
+        }
+
+        future::Either::B(next(call, meta))
+    }
+}
+
+/// Builds the `MetaIoHandler` that `start` should register all RPC
+/// delegates on, so every call they receive is metered first. Middleware is
+/// fixed at construction (unlike delegates, which `extend_with` adds
+/// afterward), so this has to be the entry point rather than a `register`
+/// taken after the fact, as `parity::register`/`parity_pubsub::register`
+/// are for their handlers.
+pub fn handler<M: Metadata + ConnectionId>(
+    options: CreditLimitOptions,
+    costs: MethodCosts,
+) -> MetaIoHandler<M, Arc<RequestMeter>> {
+    MetaIoHandler::with_middleware(RequestMeter::new(options, costs))
+}
+
+fn throttled_error() -> Error {
+    Error {
+        code: ErrorCode::ServerError(-32005),
+        message: "request throttled: not enough request credits remaining for this connection"
+            .to_string(),
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn refill_tops_up_over_time_but_not_past_the_cap() {
+        let options = CreditLimitOptions {
+            max_credits: 10.0,
+            recharge_rate: 1_000.0, // fast, so a short sleep is enough to saturate
+        };
+        let mut bucket = CreditBucket::new(&options);
+        bucket.balance = 0.0;
+
+        thread::sleep(Duration::from_millis(20));
+        bucket.refill(&options);
+
+        assert_eq!(bucket.balance, options.max_credits);
+    }
+
+    #[test]
+    fn check_charges_cost_and_throttles_once_balance_is_exhausted() {
+        let options = CreditLimitOptions {
+            max_credits: 1.0,
+            recharge_rate: 0.0, // no refill, so depletion is deterministic
+        };
+        let mut costs = MethodCosts::default();
+        costs.per_block_cost = 0.0;
+        let meter = RequestMeter::new(options, costs);
+
+        // eth_blockNumber costs 1.0; the bucket starts with exactly that.
+        assert!(meter.check("alice", "eth_blockNumber", 1).is_ok());
+        assert!(meter.check("alice", "eth_blockNumber", 1).is_err());
+    }
+
+    #[test]
+    fn check_keys_buckets_per_connection() {
+        let options = CreditLimitOptions {
+            max_credits: 1.0,
+            recharge_rate: 0.0,
+        };
+        let mut costs = MethodCosts::default();
+        costs.per_block_cost = 0.0;
+        let meter = RequestMeter::new(options, costs);
+
+        assert!(meter.check("alice", "eth_blockNumber", 1).is_ok());
+        // bob hasn't touched his bucket yet, so exhausting alice's doesn't
+        // throttle him.
+        assert!(meter.check("bob", "eth_blockNumber", 1).is_ok());
+        assert!(meter.check("alice", "eth_blockNumber", 1).is_err());
+    }
+
+    #[test]
+    fn block_count_is_one_for_non_range_methods() {
+        let params = Params::Array(vec![json!({"fromBlock": "0x1", "toBlock": "0xa"})]);
+        assert_eq!(block_count("eth_call", &params), 1);
+    }
+
+    #[test]
+    fn block_count_spans_explicit_numeric_range() {
+        let params = Params::Array(vec![json!({"fromBlock": "0x1", "toBlock": "0xa"})]);
+        assert_eq!(block_count("eth_getLogs", &params), 10);
+    }
+
+    #[test]
+    fn block_count_falls_back_to_one_for_tags_it_cant_resolve() {
+        let params = Params::Array(vec![json!({"fromBlock": "earliest", "toBlock": "latest"})]);
+        assert_eq!(block_count("eth_getLogs", &params), 1);
+    }
+
+    #[test]
+    fn block_count_falls_back_to_one_with_no_params() {
+        assert_eq!(block_count("eth_getLogs", &Params::None), 1);
+    }
+}